@@ -47,6 +47,12 @@ pub enum ErrorType {
     /// Connection removed because of a timeout
     Timeout,
 
+    /// Connection rejected by a `Filter`
+    Filtered,
+
+    /// Failed to serialize or deserialize a connection table, or to create or open its file
+    Serialization,
+
     /// Internal error which should not happen at all
     Internal,
 }
@@ -0,0 +1,139 @@
+//! CIDR based allow/deny filtering for connections
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use super::Identifier;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+/// Action taken once a `FilterRule` matches an address
+pub enum FilterAction {
+    /// The matching address is allowed
+    Allow,
+
+    /// The matching address is rejected
+    Deny,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// A single CIDR based filter rule, e.g. `10.0.0.0/8 deny`
+pub struct FilterRule {
+    network: IpAddr,
+    prefix_len: u8,
+    action: FilterAction,
+}
+
+impl FilterRule {
+    /// Create a new `FilterRule` from a network address, its prefix length and the `FilterAction`
+    /// to take once it matches. `prefix_len` is clamped to the valid range for the address
+    /// family (0-32 for IPv4, 0-128 for IPv6) so a caller-supplied out-of-range value can never
+    /// cause `matches` to compute an out-of-range shift later on.
+    pub fn new(network: IpAddr, prefix_len: u8, action: FilterAction) -> Self {
+        let max_prefix_len = match network {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        };
+        FilterRule {
+            network: network,
+            prefix_len: prefix_len.min(max_prefix_len),
+            action: action,
+        }
+    }
+
+    /// Whether the given address falls within this rule's CIDR range
+    fn matches(&self, address: &IpAddr) -> bool {
+        match (self.network, *address) {
+            (IpAddr::V4(network), IpAddr::V4(address)) => {
+                let mask = mask_u32(self.prefix_len);
+                (u32::from(network) & mask) == (u32::from(address) & mask)
+            }
+            (IpAddr::V6(network), IpAddr::V6(address)) => {
+                let mask = mask_u128(self.prefix_len);
+                (u128::from(network) & mask) == (u128::from(address) & mask)
+            }
+            _ => false,
+        }
+    }
+}
+
+fn mask_u32(prefix_len: u8) -> u32 {
+    if prefix_len == 0 {
+        0
+    } else {
+        !0u32 << (32 - prefix_len as u32)
+    }
+}
+
+fn mask_u128(prefix_len: u8) -> u128 {
+    if prefix_len == 0 {
+        0
+    } else {
+        !0u128 << (128 - prefix_len as u32)
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// Ordered set of CIDR rules gating which connections `Path::track`/`Path::track_tcp` admit
+pub struct Filter {
+    rules: Vec<FilterRule>,
+}
+
+impl Filter {
+    /// Create a new, empty `Filter`. An empty `Filter` allows every connection, rules need to be
+    /// added via `add_rule`
+    pub fn new() -> Self {
+        Filter { rules: Vec::new() }
+    }
+
+    /// Append a new rule to the end of the rule set. Rules are evaluated in insertion order, the
+    /// first one matching an address decides its fate
+    pub fn add_rule(&mut self, rule: FilterRule) -> &mut Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// Build a `Filter` which denies the commonly known RFC 1918 and other reserved IPv4 ranges
+    /// as well as IPv6 unique local addresses (`fc00::/7`), and allows everything else
+    pub fn reserved() -> Self {
+        let mut filter = Filter::new();
+        filter
+            .add_rule(FilterRule::new(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 0)), 8, FilterAction::Deny))
+            .add_rule(FilterRule::new(IpAddr::V4(Ipv4Addr::new(172, 16, 0, 0)), 12, FilterAction::Deny))
+            .add_rule(FilterRule::new(IpAddr::V4(Ipv4Addr::new(192, 168, 0, 0)), 16, FilterAction::Deny))
+            .add_rule(FilterRule::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 0)), 8, FilterAction::Deny))
+            .add_rule(FilterRule::new(IpAddr::V4(Ipv4Addr::new(169, 254, 0, 0)), 16, FilterAction::Deny))
+            .add_rule(FilterRule::new(IpAddr::V6(Ipv6Addr::new(0xfc00, 0, 0, 0, 0, 0, 0, 0)), 7, FilterAction::Deny))
+            .add_rule(FilterRule::new(IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)), 0, FilterAction::Allow));
+        filter
+    }
+
+    /// Whether both endpoints of the `Identifier` are admitted by this `Filter`
+    ///
+    /// # Examples
+    /// ```
+    /// use path::filter::{Filter, FilterRule, FilterAction};
+    /// use path::Identifier;
+    /// use std::net::{IpAddr, Ipv4Addr};
+    ///
+    /// let mut filter = Filter::new();
+    /// filter.add_rule(FilterRule::new(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 0)), 8, FilterAction::Deny));
+    ///
+    /// let identifier = Identifier::new(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)), 1234,
+    ///                                  IpAddr::V4(Ipv4Addr::new(8, 8, 8, 8)), 443,
+    ///                                  6);
+    /// assert!(!filter.admits(&identifier));
+    /// ```
+    pub fn admits<K>(&self, identifier: &Identifier<K>) -> bool {
+        self.action_for(&identifier.lower.address) == FilterAction::Allow &&
+        self.action_for(&identifier.greater.address) == FilterAction::Allow
+    }
+
+    /// The `FilterAction` decided by the first matching rule, defaulting to `Allow` if no rule
+    /// matches
+    fn action_for(&self, address: &IpAddr) -> FilterAction {
+        for rule in &self.rules {
+            if rule.matches(address) {
+                return rule.action;
+            }
+        }
+        FilterAction::Allow
+    }
+}
@@ -36,14 +36,29 @@ extern crate fnv;
 extern crate time;
 extern crate mowl;
 extern crate linked_hash_map;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde_derive;
+#[cfg(feature = "serde")]
+extern crate serde_json;
 
 #[macro_use]
 pub mod error;
 use error::{PathResult, ErrorType};
 
+pub mod filter;
+use filter::Filter;
+
+use std::collections::{HashMap, VecDeque};
 use std::fmt;
 use std::hash::{BuildHasherDefault, Hash};
 use std::net::IpAddr;
+#[cfg(feature = "serde")]
+use std::io::{Read, Write};
+#[cfg(feature = "serde")]
+use std::fs::File;
 
 use time::{Duration, precise_time_ns};
 use fnv::FnvHasher;
@@ -65,6 +80,80 @@ pub struct Path<K, C>
 
     /// Maximum amount of flows within the HashMap, per default 1 million
     pub max_connections: u64,
+
+    /// Per `TcpState` timeouts used by `track_tcp`, falling back to `timeout` for any state not
+    /// contained within this map
+    pub timeouts: HashMap<TcpState, Duration>,
+
+    /// Optional CIDR based allow/deny filter evaluated against both endpoints of a connection
+    /// before it is tracked
+    pub filter: Option<Filter>,
+
+    /// Lifecycle events accumulated during `track`/`track_tcp`/`flush`, drain with
+    /// `events.drain(..)` or similar
+    pub events: VecDeque<PathEvent<K>>,
+
+    /// Hashed timing wheel used to expire connections in amortized O(expired) instead of scanning
+    /// the whole `hashmap` on every `flush`
+    wheel: Vec<VecDeque<WheelEntry<K>>>,
+
+    /// The current tick of `wheel`, i.e. `precise_time_ns` divided by `TICK_NANOS`
+    cursor: u64,
+}
+
+/// Number of slots in the hashed timing wheel
+const WHEEL_SLOTS: u64 = 256;
+
+/// Duration of a single timing wheel tick. Millisecond resolution keeps `flush`/eviction
+/// precision in line with what `track`'s previous per-entry wall-clock comparison gave callers
+/// configuring sub-second timeouts, while still amortizing eviction across `WHEEL_SLOTS` instead
+/// of scanning the whole table.
+const TICK_NANOS: u64 = 1_000_000;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// A single entry of the hashed timing wheel
+struct WheelEntry<K> {
+    /// The connection this entry expires
+    identifier: Identifier<K>,
+
+    /// Remaining laps around the wheel before this entry actually expires, needed because a
+    /// timeout may span more ticks than the wheel has slots
+    rounds: u32,
+}
+
+/// Convert a `Duration` into a whole number of wheel ticks, at least one, so a zero/negative
+/// timeout is scheduled one tick ahead of the cursor
+fn ticks_for(timeout: Duration) -> u64 {
+    let nanos = timeout.num_nanoseconds().unwrap_or(0).max(0) as u64;
+    (nanos / TICK_NANOS).max(1)
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// Connection lifecycle transitions reported by `Path`
+pub enum PathEvent<K> {
+    /// A new connection has been inserted
+    Established(Identifier<K>),
+
+    /// A connection has been removed because of a timeout
+    TimedOut(Identifier<K>),
+
+    /// A connection has been removed to make room for a new one (LRU eviction)
+    Evicted(Identifier<K>),
+
+    /// The packet counter of a connection overflowed
+    CounterOverflow(Identifier<K>),
+}
+
+/// Build up the default set of per-`TcpState` timeouts
+fn default_tcp_timeouts() -> HashMap<TcpState, Duration> {
+    let mut timeouts = HashMap::new();
+    timeouts.insert(TcpState::SynSent, Duration::seconds(60));
+    timeouts.insert(TcpState::SynRecv, Duration::seconds(60));
+    timeouts.insert(TcpState::Established, Duration::days(5));
+    timeouts.insert(TcpState::FinWait, Duration::seconds(120));
+    timeouts.insert(TcpState::TimeWait, Duration::seconds(120));
+    timeouts.insert(TcpState::Close, Duration::seconds(10));
+    timeouts
 }
 
 impl<K, C> Path<K, C>
@@ -85,7 +174,125 @@ impl<K, C> Path<K, C>
             hashmap: HashMapFnv::default(),
             timeout: Duration::minutes(10),
             max_connections: 1_000_000, // 0 == unlimited
+            timeouts: default_tcp_timeouts(),
+            filter: None,
+            events: VecDeque::new(),
+            wheel: (0..WHEEL_SLOTS).map(|_| VecDeque::new()).collect(),
+            cursor: precise_time_ns() / TICK_NANOS,
+        }
+    }
+
+    /// Schedule `identifier` to expire after `timeout` and return the wheel slot it was placed in
+    fn schedule(&mut self, identifier: &Identifier<K>, timeout: Duration) -> usize {
+        let ticks = ticks_for(timeout);
+        let slot = ((self.cursor + ticks) % WHEEL_SLOTS) as usize;
+        let rounds = (ticks / WHEEL_SLOTS) as u32;
+        self.wheel[slot].push_back(WheelEntry {
+            identifier: identifier.clone(),
+            rounds: rounds,
+        });
+        slot
+    }
+
+    /// Move `identifier` out of `old_slot` and reschedule it to expire after `timeout`, returning
+    /// its new slot. A flow refreshed within the same tick that lands back in the same slot is
+    /// updated in place instead of being removed and reinserted.
+    fn reschedule(&mut self, identifier: &Identifier<K>, old_slot: usize, timeout: Duration) -> usize {
+        let ticks = ticks_for(timeout);
+        let new_slot = ((self.cursor + ticks) % WHEEL_SLOTS) as usize;
+        let new_rounds = (ticks / WHEEL_SLOTS) as u32;
+
+        if new_slot == old_slot {
+            if let Some(entry) = self.wheel[old_slot].iter_mut().find(|e| &e.identifier == identifier) {
+                entry.rounds = new_rounds;
+                return old_slot;
+            }
+        }
+
+        if let Some(pos) = self.wheel[old_slot].iter().position(|e| &e.identifier == identifier) {
+            self.wheel[old_slot].remove(pos);
+        }
+        self.wheel[new_slot].push_back(WheelEntry {
+            identifier: identifier.clone(),
+            rounds: new_rounds,
+        });
+        new_slot
+    }
+
+    /// Remove the `WheelEntry` for `identifier` out of `slot`, if it is still there. Must be
+    /// called whenever a connection is removed from `hashmap` through any path other than the
+    /// wheel itself (inline timeout, LRU eviction, `Path::remove`), otherwise the stale entry
+    /// stays queued and a later `advance_wheel` can evict a since re-tracked, still-valid
+    /// connection with the same `Identifier` out from under it.
+    fn cancel(&mut self, identifier: &Identifier<K>, slot: usize) {
+        if let Some(pos) = self.wheel[slot].iter().position(|e| &e.identifier == identifier) {
+            self.wheel[slot].remove(pos);
+        }
+    }
+
+    /// Re-anchor a refreshed connection's `WheelEntry` at its new timeout and persist the
+    /// resulting slot back onto its `Data`. Shared by `track`/`track_tcp`/`track_bytes` once each
+    /// has updated its own counters and (for `track_tcp`) advanced the `TcpState`.
+    fn touch(&mut self, identifier: &Identifier<K>, old_slot: usize, new_timeout: Duration) {
+        let new_slot = self.reschedule(identifier, old_slot, new_timeout);
+        if let Some(data) = self.hashmap.get_refresh(identifier) {
+            data.wheel_slot = new_slot;
+        }
+    }
+
+    /// Remove a timed-out connection and its stale `WheelEntry`, recording a `TimedOut` event.
+    /// Shared by `track`/`track_tcp`/`track_bytes`; the caller still needs to `bail!` afterwards.
+    fn expire(&mut self, identifier: &Identifier<K>, slot: Option<usize>) {
+        self.hashmap.remove(identifier);
+        if let Some(slot) = slot {
+            self.cancel(identifier, slot);
+        }
+        self.events.push_back(PathEvent::TimedOut(identifier.clone()));
+        warn!("Connection removed (timeout): {}", identifier);
+    }
+
+    /// Finish inserting a brand-new connection: evict the oldest entry via LRU if the table is
+    /// already full, schedule `data` into the timing wheel and record the `Established` event.
+    /// Shared by `track`/`track_tcp`/`track_bytes`, which differ only in how `data` is built up.
+    fn insert_new(&mut self, identifier: Identifier<K>, mut data: Data<C>, timeout: Duration) {
+        if self.max_connections > 0 && self.hashmap.len() as u64 >= self.max_connections {
+            let removed = self.hashmap.pop_front().unwrap();
+            self.cancel(&removed.0, removed.1.wheel_slot);
+            self.events.push_back(PathEvent::Evicted(removed.0.clone()));
+            warn!("Connection removed (HashMap full): {}", removed.0);
         }
+
+        data.wheel_slot = self.schedule(&identifier, timeout);
+        debug!("Connection inserted: {}", identifier);
+        self.events.push_back(PathEvent::Established(identifier.clone()));
+        self.hashmap.insert(identifier, data);
+    }
+
+    /// Advance the wheel's cursor up to `now`, evicting every entry whose rounds reached zero
+    /// along the way and returning their identifiers
+    fn advance_wheel(&mut self, now: u64) -> Vec<Identifier<K>> {
+        let current_tick = now / TICK_NANOS;
+        let mut expired = Vec::new();
+
+        while self.cursor < current_tick {
+            let slot = (self.cursor % WHEEL_SLOTS) as usize;
+            let pending: Vec<WheelEntry<K>> = self.wheel[slot].drain(..).collect();
+            for mut entry in pending {
+                if entry.rounds == 0 {
+                    if self.hashmap.remove(&entry.identifier).is_some() {
+                        self.events.push_back(PathEvent::TimedOut(entry.identifier.clone()));
+                        warn!("Connection removed (timeout): {}", entry.identifier);
+                        expired.push(entry.identifier);
+                    }
+                } else {
+                    entry.rounds -= 1;
+                    self.wheel[slot].push_back(entry);
+                }
+            }
+            self.cursor += 1;
+        }
+
+        expired
     }
 
     /// Set the global log level for reporting
@@ -128,48 +335,61 @@ impl<K, C> Path<K, C>
     /// assert_eq!(connection.data.packet_counter(), 1);
     /// ```
     pub fn track(&mut self, identifier: Identifier<K>) -> PathResult<Connection<K, C>> {
-        // Get the current timestamp
+        // Reject the flow right away if it does not pass the configured filter
+        if let Some(ref filter) = self.filter {
+            if !filter.admits(&identifier) {
+                bail!(ErrorType::Filtered, "Connection rejected by filter: {}", identifier);
+            }
+        }
+
+        // Get the current timestamp and let the timing wheel evict anything already due,
+        // reporting the timeout if this very connection was among the evicted ones
         let now = precise_time_ns();
+        if self.advance_wheel(now).contains(&identifier) {
+            bail!(ErrorType::Timeout, "Connection removed because of timeout");
+        }
 
         // Check if the entry already exists and retrieve a connection state
+        let mut refreshed_slot = None;
+        let mut timeout_slot = None;
         let connection_state = match self.hashmap.get_refresh(&identifier) {
             Some(data) => {
                 if Duration::nanoseconds((now - data.timestamp) as i64) <= self.timeout {
-                    match data.packet_counter.checked_add(1) {
-                        Some(value) => data.packet_counter = value,
-                        None => bail!(ErrorType::PacketCounterOverflow, "Packet counter overflow"),
+                    match data.orig_packets.checked_add(1) {
+                        Some(value) => data.orig_packets = value,
+                        None => {
+                            self.events.push_back(PathEvent::CounterOverflow(identifier.clone()));
+                            bail!(ErrorType::PacketCounterOverflow, "Packet counter overflow");
+                        }
                     }
                     data.timestamp = now;
+                    refreshed_slot = Some(data.wheel_slot);
                     ConnectionState::Ok
                 } else {
+                    timeout_slot = Some(data.wheel_slot);
                     ConnectionState::Timeout
                 }
             }
             None => ConnectionState::New,
         };
 
+        // Re-anchor the refreshed entry's position in the timing wheel now that `hashmap` is free
+        if let Some(old_slot) = refreshed_slot {
+            self.touch(&identifier, old_slot, self.timeout);
+        }
+
         // Do something based on the connection state
         match connection_state {
 
             // Connection timeout happened
             ConnectionState::Timeout => {
-                self.hashmap.remove(&identifier);
-                warn!("Connection removed (timeout): {}", identifier);
+                self.expire(&identifier, timeout_slot);
                 bail!(ErrorType::Timeout, "Connection removed because of timeout");
             }
 
             // Add a new connection
             ConnectionState::New => {
-                // But check first if the HashMap contains available free slots
-                if self.max_connections > 0 && self.hashmap.len() as u64 >= self.max_connections {
-                    // Remove the oldest not active element from the table (LRU cache)
-                    let removed = self.hashmap.pop_front();
-                    warn!("Connection removed (HashMap full): {}", removed.unwrap().0);
-                }
-
-                // Insert a new connection
-                debug!("Connection inserted: {}", identifier);
-                self.hashmap.insert(identifier, Data::new());
+                self.insert_new(identifier.clone(), Data::new(), self.timeout);
             }
 
             // We just need to return a mutable reference to the HashMap value
@@ -181,6 +401,232 @@ impl<K, C> Path<K, C>
         Ok(self.last_mut().unwrap())
     }
 
+    /// Track a TCP connection based on its `Identifier`, the flags carried by the current packet
+    /// and the address/port the packet originated from. The connection moves through a real TCP
+    /// state machine (`TcpState`) instead of being compared against a single flat `timeout`,
+    /// which is looked up per state from `timeouts`.
+    ///
+    /// # Examples
+    /// ```
+    /// use path::{Path, Identifier, TcpFlags};
+    /// use std::net::{IpAddr, Ipv4Addr};
+    ///
+    /// let mut path: Path<u8, u8> = Path::new();
+    /// let source = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+    /// let identifier = Identifier::new(source, 1234,
+    ///                                  IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)), 443,
+    ///                                  6);
+    /// let syn = TcpFlags { syn: true, ..Default::default() };
+    /// let connection = path.track_tcp(identifier, syn, source, 1234).unwrap();
+    /// assert_eq!(connection.data.tcp_state(), Some(TcpState::SynSent));
+    /// # use path::TcpState;
+    /// ```
+    pub fn track_tcp(&mut self,
+                      identifier: Identifier<K>,
+                      flags: TcpFlags,
+                      source_ip: IpAddr,
+                      source_port: u16)
+                      -> PathResult<Connection<K, C>> {
+        // Reject the flow right away if it does not pass the configured filter
+        if let Some(ref filter) = self.filter {
+            if !filter.admits(&identifier) {
+                bail!(ErrorType::Filtered, "Connection rejected by filter: {}", identifier);
+            }
+        }
+
+        // Get the current timestamp and let the timing wheel evict anything already due,
+        // reporting the timeout if this very connection was among the evicted ones
+        let now = precise_time_ns();
+        if self.advance_wheel(now).contains(&identifier) {
+            bail!(ErrorType::Timeout, "Connection removed because of timeout");
+        }
+
+        // The packet's side is recovered by comparing the observed source address and port
+        // against the normalized `lower` endpoint of the identifier
+        let side = if source_is_lower(&identifier, source_ip, source_port) {
+            Side::Lower
+        } else {
+            Side::Greater
+        };
+
+        let mut refreshed = None;
+        let mut timeout_slot = None;
+        let connection_state = match self.hashmap.get_refresh(&identifier) {
+            Some(data) => {
+                let state = data.tcp_state.unwrap_or(TcpState::Established);
+                let timeout = self.timeouts.get(&state).cloned().unwrap_or(self.timeout);
+                if Duration::nanoseconds((now - data.timestamp) as i64) <= timeout {
+                    let counter = match side {
+                        Side::Lower => &mut data.orig_packets,
+                        Side::Greater => &mut data.reply_packets,
+                    };
+                    match counter.checked_add(1) {
+                        Some(value) => *counter = value,
+                        None => {
+                            self.events.push_back(PathEvent::CounterOverflow(identifier.clone()));
+                            bail!(ErrorType::PacketCounterOverflow, "Packet counter overflow");
+                        }
+                    }
+                    data.timestamp = now;
+                    data.tcp_state = Some(next_tcp_state(data.tcp_state, flags, side, &mut data.fin_sender));
+                    let new_timeout = self.timeouts
+                        .get(&data.tcp_state.unwrap())
+                        .cloned()
+                        .unwrap_or(self.timeout);
+                    refreshed = Some((data.wheel_slot, new_timeout));
+                    ConnectionState::Ok
+                } else {
+                    timeout_slot = Some(data.wheel_slot);
+                    ConnectionState::Timeout
+                }
+            }
+            None => ConnectionState::New,
+        };
+
+        // Re-anchor the refreshed entry's position in the timing wheel now that `hashmap` is free
+        if let Some((old_slot, new_timeout)) = refreshed {
+            self.touch(&identifier, old_slot, new_timeout);
+        }
+
+        match connection_state {
+            ConnectionState::Timeout => {
+                self.expire(&identifier, timeout_slot);
+                bail!(ErrorType::Timeout, "Connection removed because of timeout");
+            }
+
+            ConnectionState::New => {
+                let mut data = Data::new();
+                if side == Side::Greater {
+                    // `Data::new` counts the opening packet in the original direction
+                    data.orig_packets = 0;
+                    data.reply_packets = 1;
+                }
+                let mut fin_sender = None;
+                let new_state = next_tcp_state(None, flags, side, &mut fin_sender);
+                data.tcp_state = Some(new_state);
+                data.fin_sender = fin_sender;
+                let timeout = self.timeouts.get(&new_state).cloned().unwrap_or(self.timeout);
+                self.insert_new(identifier.clone(), data, timeout);
+            }
+
+            ConnectionState::Ok => {}
+        }
+
+        Ok(self.last_mut().unwrap())
+    }
+
+    /// Track a connection while accounting for an observed byte count, split into the four
+    /// `Data` totals (`orig_packets`/`orig_bytes`/`reply_packets`/`reply_bytes`) based on the
+    /// `Direction` the packet was observed in
+    ///
+    /// # Examples
+    /// ```
+    /// use path::{Path, Identifier};
+    /// use std::net::{IpAddr, Ipv4Addr};
+    ///
+    /// let mut path: Path<u8, u8> = Path::new();
+    /// let source = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+    /// let identifier = Identifier::new(source, 1234,
+    ///                                  IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)), 443,
+    ///                                  6);
+    /// let connection = path.track_bytes(identifier, 1500, source, 1234).unwrap();
+    /// assert_eq!(connection.data.orig_bytes(), 1500);
+    /// ```
+    pub fn track_bytes(&mut self,
+                        identifier: Identifier<K>,
+                        bytes: u64,
+                        source_ip: IpAddr,
+                        source_port: u16)
+                        -> PathResult<Connection<K, C>> {
+        // Reject the flow right away if it does not pass the configured filter
+        if let Some(ref filter) = self.filter {
+            if !filter.admits(&identifier) {
+                bail!(ErrorType::Filtered, "Connection rejected by filter: {}", identifier);
+            }
+        }
+
+        // Get the current timestamp and let the timing wheel evict anything already due,
+        // reporting the timeout if this very connection was among the evicted ones
+        let now = precise_time_ns();
+        if self.advance_wheel(now).contains(&identifier) {
+            bail!(ErrorType::Timeout, "Connection removed because of timeout");
+        }
+
+        let direction = direction_of(&identifier, source_ip, source_port);
+
+        let mut refreshed_slot = None;
+        let mut timeout_slot = None;
+        let connection_state = match self.hashmap.get_refresh(&identifier) {
+            Some(data) => {
+                if Duration::nanoseconds((now - data.timestamp) as i64) <= self.timeout {
+                    match direction {
+                        Direction::Original => {
+                            match data.orig_packets.checked_add(1) {
+                                Some(value) => data.orig_packets = value,
+                                None => {
+                                    self.events.push_back(PathEvent::CounterOverflow(identifier.clone()));
+                                    bail!(ErrorType::PacketCounterOverflow, "Packet counter overflow");
+                                }
+                            }
+                            data.orig_bytes = data.orig_bytes.saturating_add(bytes);
+                        }
+                        Direction::Reply => {
+                            match data.reply_packets.checked_add(1) {
+                                Some(value) => data.reply_packets = value,
+                                None => {
+                                    self.events.push_back(PathEvent::CounterOverflow(identifier.clone()));
+                                    bail!(ErrorType::PacketCounterOverflow, "Packet counter overflow");
+                                }
+                            }
+                            data.reply_bytes = data.reply_bytes.saturating_add(bytes);
+                        }
+                    }
+                    data.last_bytes = bytes;
+                    data.last_elapsed_ns = now - data.timestamp;
+                    data.timestamp = now;
+                    refreshed_slot = Some(data.wheel_slot);
+                    ConnectionState::Ok
+                } else {
+                    timeout_slot = Some(data.wheel_slot);
+                    ConnectionState::Timeout
+                }
+            }
+            None => ConnectionState::New,
+        };
+
+        // Re-anchor the refreshed entry's position in the timing wheel now that `hashmap` is free
+        if let Some(old_slot) = refreshed_slot {
+            self.touch(&identifier, old_slot, self.timeout);
+        }
+
+        match connection_state {
+            ConnectionState::Timeout => {
+                self.expire(&identifier, timeout_slot);
+                bail!(ErrorType::Timeout, "Connection removed because of timeout");
+            }
+
+            ConnectionState::New => {
+                let mut data = Data::new();
+                data.last_bytes = bytes;
+                match direction {
+                    Direction::Original => data.orig_bytes = bytes,
+                    Direction::Reply => {
+                        // `Data::new` assumes the opening packet is in the original direction;
+                        // correct that when the very first observed packet is actually a reply
+                        data.orig_packets = 0;
+                        data.reply_packets = 1;
+                        data.reply_bytes = bytes;
+                    }
+                }
+                self.insert_new(identifier.clone(), data, self.timeout);
+            }
+
+            ConnectionState::Ok => {}
+        }
+
+        Ok(self.last_mut().unwrap())
+    }
+
     /// Get the number of actual tracked connections
     pub fn connection_count(&self) -> usize {
         self.hashmap.len()
@@ -190,6 +636,122 @@ impl<K, C> Path<K, C>
     pub fn last_mut(&mut self) -> Option<Connection<K, C>> {
         self.hashmap.iter_mut().rev().next().map(|(i, d)| Connection::new(i, d))
     }
+
+    /// Remove a connection from the table, without emitting a `PathEvent`
+    pub fn remove(&mut self, identifier: &Identifier<K>) -> Option<Data<C>> {
+        let data = self.hashmap.remove(identifier);
+        if let Some(ref data) = data {
+            self.cancel(identifier, data.wheel_slot);
+        }
+        data
+    }
+
+    /// Evict every connection whose timeout has been exceeded, emitting a `PathEvent::TimedOut`
+    /// for each and returning their identifiers. Driven by the hashed timing wheel, this only
+    /// touches the connections due to expire since the last call instead of scanning the whole
+    /// table.
+    pub fn flush(&mut self) -> Vec<Identifier<K>> {
+        let now = precise_time_ns();
+        self.advance_wheel(now)
+    }
+
+    /// Serialize the connection table to `writer`, alongside the `precise_time_ns` value at the
+    /// time of the save, which `load_from` uses to recover each entry's age
+    ///
+    /// Requires the `serde` feature.
+    #[cfg(feature = "serde")]
+    pub fn save_to<W>(&self, writer: W) -> PathResult<()>
+        where W: Write,
+              K: ::serde::Serialize,
+              C: ::serde::Serialize
+    {
+        let table = PersistedTable {
+            anchor_ns: precise_time_ns(),
+            entries: self.hashmap.iter().map(|(i, d)| (i.clone(), d.clone())).collect(),
+        };
+        serde_json::to_writer(writer, &table)
+            .map_err(|e| error::bail(ErrorType::Serialization, &e))
+    }
+
+    /// Deserialize a connection table from `reader` and merge it into this `Path`. Every entry
+    /// keeps the age it had when saved: its timestamp is rebased against the current monotonic
+    /// clock, and an entry already past its timeout at save time is dropped. Time that passed
+    /// between the save and the load is not counted towards any entry's age.
+    ///
+    /// Requires the `serde` feature.
+    #[cfg(feature = "serde")]
+    pub fn load_from<R>(&mut self, reader: R) -> PathResult<()>
+        where R: Read,
+              K: ::serde::de::DeserializeOwned,
+              C: ::serde::de::DeserializeOwned
+    {
+        let table: PersistedTable<K, C> = serde_json::from_reader(reader)
+            .map_err(|e| error::bail(ErrorType::Serialization, &e))?;
+        let now = precise_time_ns();
+
+        // Resync the wheel's cursor to the current tick first. Otherwise, if this `Path` has sat
+        // idle since it was created, `schedule` below would place entries against a stale
+        // cursor, and the very next `track`/`flush` call would fast-forward through all the
+        // intervening ticks and immediately expire the freshly-restored connections.
+        self.advance_wheel(now);
+
+        for (identifier, mut data) in table.entries {
+            let age_ns = table.anchor_ns.saturating_sub(data.timestamp);
+            let timeout = match data.tcp_state {
+                Some(state) => self.timeouts.get(&state).cloned().unwrap_or(self.timeout),
+                None => self.timeout,
+            };
+            let age = Duration::nanoseconds(age_ns as i64);
+            if age > timeout {
+                continue;
+            }
+            data.timestamp = now.saturating_sub(age_ns);
+            data.wheel_slot = self.schedule(&identifier, timeout - age);
+            self.hashmap.insert(identifier, data);
+        }
+
+        Ok(())
+    }
+
+    /// Convenience wrapper around `save_to` which writes the connection table to the file at
+    /// `path`
+    ///
+    /// Requires the `serde` feature.
+    #[cfg(feature = "serde")]
+    pub fn save_to_file<P>(&self, path: P) -> PathResult<()>
+        where P: AsRef<::std::path::Path>,
+              K: ::serde::Serialize,
+              C: ::serde::Serialize
+    {
+        let file = File::create(path).map_err(|e| error::bail(ErrorType::Serialization, &e))?;
+        self.save_to(file)
+    }
+
+    /// Convenience wrapper around `load_from` which reads the connection table from the file at
+    /// `path`
+    ///
+    /// Requires the `serde` feature.
+    #[cfg(feature = "serde")]
+    pub fn load_from_file<P>(&mut self, path: P) -> PathResult<()>
+        where P: AsRef<::std::path::Path>,
+              K: ::serde::de::DeserializeOwned,
+              C: ::serde::de::DeserializeOwned
+    {
+        let file = File::open(path).map_err(|e| error::bail(ErrorType::Serialization, &e))?;
+        self.load_from(file)
+    }
+}
+
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+/// On-disk representation of a `Path`'s connection table
+struct PersistedTable<K, C> {
+    /// `precise_time_ns` (a monotonic clock, not wall-clock time) at the moment of the save; the
+    /// stored timestamps are relative to it
+    anchor_ns: u64,
+
+    /// The persisted connections
+    entries: Vec<(Identifier<K>, Data<C>)>,
 }
 
 #[derive(Debug, Eq, PartialEq)]
@@ -216,11 +778,18 @@ impl<'a, 'b, K, C> fmt::Display for Connection<'a, 'b, K, C>
     where K: fmt::Debug
 {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.identifier)
+        write!(f,
+               "{} (orig: {} packets / {} bytes, reply: {} packets / {} bytes)",
+               self.identifier,
+               self.data.orig_packets(),
+               self.data.orig_bytes(),
+               self.data.reply_packets(),
+               self.data.reply_bytes())
     }
 }
 
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 /// Identifies the connection
 pub struct Identifier<K> {
     /// Lower subscriber
@@ -270,6 +839,7 @@ impl<K: fmt::Debug> fmt::Display for Identifier<K> {
 }
 
 #[derive(Clone, Debug, Hash, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 /// Identifies the connection endpoints
 pub struct Subscriber {
     /// Address of the subscriber
@@ -280,31 +850,242 @@ pub struct Subscriber {
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 /// Identifies the connection
 pub struct Data<C> {
     /// Data from the user
     pub custom: Option<C>,
 
-    /// The packet counter for the connection
-    packet_counter: u64,
+    /// Packets observed in the original direction (towards `Identifier::greater`).
+    /// `Path::track` has no direction information and counts every packet here.
+    orig_packets: u64,
+
+    /// Bytes observed in the original direction (towards `Identifier::greater`)
+    orig_bytes: u64,
+
+    /// Packets observed in the reply direction (towards `Identifier::lower`)
+    reply_packets: u64,
+
+    /// Bytes observed in the reply direction (towards `Identifier::lower`)
+    reply_bytes: u64,
+
+    /// Bytes observed during the most recent call to `Path::track_bytes`, used to derive
+    /// `throughput`
+    last_bytes: u64,
+
+    /// Nanoseconds elapsed since the previous access when `last_bytes` was observed
+    last_elapsed_ns: u64,
 
     /// Last accessed timestamp
     timestamp: u64,
+
+    /// The current TCP state, only populated for connections tracked via `Path::track_tcp`
+    tcp_state: Option<TcpState>,
+
+    /// The side which sent the first FIN, needed to recognize the FIN from the other side during
+    /// teardown
+    fin_sender: Option<Side>,
+
+    /// The slot of the owning `Path`'s timing wheel this connection is currently scheduled in.
+    /// Not meaningful across a save/load round-trip, so it is never serialized.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    wheel_slot: usize,
 }
 
 impl<C> Data<C> {
     /// Create new connection data
     pub fn new() -> Self {
         Data {
-            packet_counter: 1,
+            orig_packets: 1,
+            orig_bytes: 0,
+            reply_packets: 0,
+            reply_bytes: 0,
+            last_bytes: 0,
+            last_elapsed_ns: 0,
             timestamp: precise_time_ns(),
             custom: None,
+            tcp_state: None,
+            fin_sender: None,
+            wheel_slot: 0,
         }
     }
 
-    /// Retrieve the current packet counter value
+    /// Retrieve the current packet counter value, the sum of `orig_packets` and `reply_packets`.
+    /// Kept for backward compatibility with code that does not care about direction.
     pub fn packet_counter(&self) -> u64 {
-        self.packet_counter
+        self.orig_packets + self.reply_packets
+    }
+
+    /// Packets observed in the original direction (towards `Identifier::greater`).
+    /// `Path::track` has no direction information and counts every packet here.
+    pub fn orig_packets(&self) -> u64 {
+        self.orig_packets
+    }
+
+    /// Bytes observed in the original direction (towards `Identifier::greater`)
+    pub fn orig_bytes(&self) -> u64 {
+        self.orig_bytes
+    }
+
+    /// Packets observed in the reply direction (towards `Identifier::lower`)
+    pub fn reply_packets(&self) -> u64 {
+        self.reply_packets
+    }
+
+    /// Bytes observed in the reply direction (towards `Identifier::lower`)
+    pub fn reply_bytes(&self) -> u64 {
+        self.reply_bytes
+    }
+
+    /// Derived throughput estimate in bytes per nanosecond, based on the bytes observed during
+    /// the most recent call to `Path::track_bytes` and the time elapsed since the previous access
+    pub fn throughput(&self) -> f64 {
+        if self.last_elapsed_ns == 0 {
+            0.0
+        } else {
+            self.last_bytes as f64 / self.last_elapsed_ns as f64
+        }
+    }
+
+    /// Retrieve the current `TcpState`, if this connection is being tracked via `track_tcp`
+    pub fn tcp_state(&self) -> Option<TcpState> {
+        self.tcp_state
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// States of the TCP connection state machine driven by `Path::track_tcp`
+pub enum TcpState {
+    /// Initial SYN has been observed on a new flow
+    SynSent,
+
+    /// The reply SYN+ACK has been observed
+    SynRecv,
+
+    /// The handshake completed with a bare ACK
+    Established,
+
+    /// A FIN has been observed from either side
+    FinWait,
+
+    /// Both sides completed their FIN+ACK exchange
+    TimeWait,
+
+    /// A RST has been observed, the connection is closed
+    Close,
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+/// TCP flag bits relevant for driving the `TcpState` state machine
+pub struct TcpFlags {
+    /// SYN flag
+    pub syn: bool,
+
+    /// ACK flag
+    pub ack: bool,
+
+    /// FIN flag
+    pub fin: bool,
+
+    /// RST flag
+    pub rst: bool,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// The side of an `Identifier` a packet originated from
+enum Side {
+    /// The packet originated from the `lower` endpoint
+    Lower,
+
+    /// The packet originated from the `greater` endpoint
+    Greater,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// Direction of a packet relative to an `Identifier`'s normalized endpoints, in the same sense
+/// conntrack uses "original"/"reply"
+pub enum Direction {
+    /// The packet originated from the `lower` endpoint
+    Original,
+
+    /// The packet originated from the `greater` endpoint
+    Reply,
+}
+
+/// Whether `(source_ip, source_port)` is the normalized `lower` endpoint of `identifier`. Both
+/// the address and the port must be compared: endpoints commonly share an address (loopback,
+/// NAT, two ends of a connection behind the same gateway) and differ only by port.
+fn source_is_lower<K>(identifier: &Identifier<K>, source_ip: IpAddr, source_port: u16) -> bool {
+    (source_ip, source_port) == (identifier.lower.address, identifier.lower.port)
+}
+
+/// Recover the `Direction` of a packet by comparing its observed source address and port against
+/// the normalized `lower` endpoint of the identifier
+fn direction_of<K>(identifier: &Identifier<K>, source_ip: IpAddr, source_port: u16) -> Direction {
+    if source_is_lower(identifier, source_ip, source_port) {
+        Direction::Original
+    } else {
+        Direction::Reply
+    }
+}
+
+/// Drive the `TcpState` state machine forward based on the current state, the flags carried by
+/// the current packet and the side it was observed from. Tracks which side sent the first FIN in
+/// `fin_sender` so teardown only completes once the other side's FIN+ACK is seen.
+fn next_tcp_state(current: Option<TcpState>,
+                   flags: TcpFlags,
+                   side: Side,
+                   fin_sender: &mut Option<Side>)
+                   -> TcpState {
+    if flags.rst {
+        return TcpState::Close;
+    }
+
+    match current {
+        None => {
+            if flags.syn && !flags.ack {
+                TcpState::SynSent
+            } else {
+                TcpState::Established
+            }
+        }
+        Some(TcpState::SynSent) => {
+            if flags.syn && flags.ack {
+                TcpState::SynRecv
+            } else {
+                TcpState::SynSent
+            }
+        }
+        Some(TcpState::SynRecv) => {
+            if flags.ack && !flags.syn {
+                TcpState::Established
+            } else {
+                TcpState::SynRecv
+            }
+        }
+        Some(TcpState::Established) => {
+            if flags.fin {
+                *fin_sender = Some(side);
+                TcpState::FinWait
+            } else {
+                TcpState::Established
+            }
+        }
+        Some(TcpState::FinWait) => {
+            if flags.fin && flags.ack {
+                match *fin_sender {
+                    Some(sender) if sender != side => TcpState::TimeWait,
+                    _ => TcpState::FinWait,
+                }
+            } else {
+                TcpState::FinWait
+            }
+        }
+        Some(TcpState::TimeWait) => TcpState::TimeWait,
+        Some(TcpState::Close) => TcpState::Close,
     }
 }
 
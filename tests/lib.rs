@@ -1,6 +1,7 @@
 extern crate path;
-use path::{Path, Connection, Identifier, Data};
+use path::{Path, Connection, Identifier, Data, TcpFlags, TcpState, PathEvent};
 use path::error::ErrorType;
+use path::filter::{Filter, FilterRule, FilterAction};
 
 use std::net::{IpAddr, Ipv4Addr};
 use std::error::Error;
@@ -134,6 +135,135 @@ fn path_failure_compare_connection() {
     assert!(c1 != c2);
 }
 
+#[test]
+fn path_success_track_bytes_directions() {
+    let mut path: Path<u8, ()> = Path::new().set_log_level(LogLevel::Trace);
+    let identifier = get_identifier();
+    // Both endpoints share the same address (0.0.0.0, as in NAT/loopback deployments); only the
+    // port distinguishes the `lower` and `greater` ends of the connection
+    let lower_address = identifier.lower.address;
+    let lower_port = identifier.lower.port;
+    let greater_address = identifier.greater.address;
+    let greater_port = identifier.greater.port;
+
+    let connection = path.track_bytes(identifier.clone(), 100, lower_address, lower_port).unwrap();
+    assert_eq!(connection.data.orig_packets(), 1);
+    assert_eq!(connection.data.orig_bytes(), 100);
+    assert_eq!(connection.data.reply_packets(), 0);
+    assert_eq!(connection.data.reply_bytes(), 0);
+
+    let connection = path.track_bytes(identifier.clone(), 200, greater_address, greater_port).unwrap();
+    assert_eq!(connection.data.orig_packets(), 1);
+    assert_eq!(connection.data.orig_bytes(), 100);
+    assert_eq!(connection.data.reply_packets(), 1);
+    assert_eq!(connection.data.reply_bytes(), 200);
+    assert_eq!(connection.data.packet_counter(), 2);
+
+    let connection = path.track_bytes(identifier.clone(), 50, lower_address, lower_port).unwrap();
+    assert_eq!(connection.data.orig_bytes(), 150);
+    assert_eq!(connection.data.reply_bytes(), 200);
+}
+
+#[test]
+fn path_success_events() {
+    let mut path: Path<u8, ()> = Path::new().set_log_level(LogLevel::Trace);
+    path.timeout = Duration::milliseconds(1);
+    path.max_connections = 1;
+    let first_identifier = get_identifier();
+    let mut second_identifier = get_identifier();
+    second_identifier.lower.port += 1;
+
+    // Establish the first connection, then evict it via the LRU cap when the second arrives
+    assert!(path.track(first_identifier.clone()).is_ok());
+    assert!(path.track(second_identifier.clone()).is_ok());
+
+    assert_eq!(path.events.pop_front(), Some(PathEvent::Established(first_identifier.clone())));
+    assert_eq!(path.events.pop_front(), Some(PathEvent::Evicted(first_identifier)));
+    assert_eq!(path.events.pop_front(), Some(PathEvent::Established(second_identifier.clone())));
+    assert!(path.events.is_empty());
+
+    // Time out the remaining connection
+    std::thread::sleep(std::time::Duration::from_millis(10));
+    assert!(path.track(second_identifier.clone()).is_err());
+    assert_eq!(path.events.pop_front(), Some(PathEvent::TimedOut(second_identifier)));
+    assert!(path.events.is_empty());
+}
+
+#[test]
+fn path_success_tcp_state_machine() {
+    let mut path: Path<u8, ()> = Path::new().set_log_level(LogLevel::Trace);
+    let identifier = get_identifier();
+    // Both endpoints share the same address (0.0.0.0, as in NAT/loopback deployments); only the
+    // port distinguishes the `lower` and `greater` ends of the connection
+    let lower_address = identifier.lower.address;
+    let lower_port = identifier.lower.port;
+    let greater_address = identifier.greater.address;
+    let greater_port = identifier.greater.port;
+
+    let syn = TcpFlags { syn: true, ..Default::default() };
+    let connection = path.track_tcp(identifier.clone(), syn, greater_address, greater_port).unwrap();
+    assert_eq!(connection.data.tcp_state(), Some(TcpState::SynSent));
+
+    let syn_ack = TcpFlags { syn: true, ack: true, ..Default::default() };
+    let connection = path.track_tcp(identifier.clone(), syn_ack, lower_address, lower_port).unwrap();
+    assert_eq!(connection.data.tcp_state(), Some(TcpState::SynRecv));
+
+    let ack = TcpFlags { ack: true, ..Default::default() };
+    let connection = path.track_tcp(identifier.clone(), ack, greater_address, greater_port).unwrap();
+    assert_eq!(connection.data.tcp_state(), Some(TcpState::Established));
+
+    let fin = TcpFlags { fin: true, ack: true, ..Default::default() };
+    let connection = path.track_tcp(identifier.clone(), fin, greater_address, greater_port).unwrap();
+    assert_eq!(connection.data.tcp_state(), Some(TcpState::FinWait));
+
+    let fin_ack = TcpFlags { fin: true, ack: true, ..Default::default() };
+    let connection = path.track_tcp(identifier.clone(), fin_ack, lower_address, lower_port).unwrap();
+    assert_eq!(connection.data.tcp_state(), Some(TcpState::TimeWait));
+}
+
+#[test]
+fn path_failure_filtered() {
+    let mut filter = Filter::new();
+    filter.add_rule(FilterRule::new(IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)), 8, FilterAction::Deny));
+
+    let mut path: Path<u8, ()> = Path::new().set_log_level(LogLevel::Trace);
+    path.filter = Some(filter);
+    let identifier = get_identifier();
+
+    let res = path.track(identifier);
+    assert!(res.is_err());
+    if let Err(e) = res {
+        assert_eq!(e.code, ErrorType::Filtered);
+    }
+}
+
+#[test]
+fn path_success_reuse_after_timeout_does_not_evict_new_connection() {
+    let mut path: Path<u8, ()> = Path::new().set_log_level(LogLevel::Trace);
+    let identifier = get_identifier();
+    path.timeout = Duration::milliseconds(1);
+
+    // The first connection times out almost immediately; its `WheelEntry` is scheduled one
+    // tick (~1ms) ahead, so the next `track` reports the timeout and removes it
+    assert!(path.track(identifier.clone()).is_ok());
+    std::thread::sleep(std::time::Duration::from_millis(10));
+    assert!(path.track(identifier.clone()).is_err());
+    assert_eq!(path.connection_count(), 0);
+
+    // Re-track the exact same identifier with a generous timeout, so its own wheel entry is
+    // scheduled well past the first connection's tick
+    path.timeout = Duration::seconds(10);
+    assert!(path.track(identifier.clone()).is_ok());
+    assert_eq!(path.connection_count(), 1);
+
+    // Advance real time past the first connection's tick but nowhere near the new connection's
+    // actual timeout; the new connection must survive
+    std::thread::sleep(std::time::Duration::from_millis(1100));
+    path.flush();
+    assert_eq!(path.connection_count(), 1);
+    assert!(path.last_mut().is_some());
+}
+
 #[test]
 fn path_failure_timeout() {
     let mut path: Path<u8, ()> = Path::new().set_log_level(LogLevel::Trace);